@@ -0,0 +1,69 @@
+use glam::{Mat4, Vec3};
+use wgpu::util::DeviceExt as _;
+
+/// The camera a frame is rendered from, reduced to what every drawable's vertex/fragment shader
+/// needs: a clip-from-world matrix to place geometry, and a world-space position for lighting's
+/// view direction.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub clip_from_world: Mat4,
+    pub world_position: Vec3,
+}
+
+impl Camera {
+    fn to_uniform(self) -> CameraUniform {
+        CameraUniform {
+            clip_from_world: self.clip_from_world.to_cols_array_2d(),
+            world_position: self.world_position.into(),
+            _padding: 0.0,
+        }
+    }
+
+    /// Bind group layout shared by every pipeline that reads the camera (matches `CameraUniform`
+    /// at `@binding(0)` of `@group(0)` in `shader/pbr.wgsl` and of `@group(1)` in
+    /// `shader/box_instanced.wgsl`).
+    pub(crate) fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("camera_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    /// Upload this frame's camera into a fresh uniform buffer and bind group.
+    pub(crate) fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("camera_uniform"),
+            contents: bytemuck::bytes_of(&self.to_uniform()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("camera_bind_group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    clip_from_world: [[f32; 4]; 4],
+    world_position: [f32; 3],
+    _padding: f32,
+}
@@ -0,0 +1,14 @@
+//! Rerun's little wgpu-based renderer.
+
+mod camera;
+mod material;
+mod size;
+
+pub mod light;
+pub mod renderer;
+pub mod shadow;
+
+pub use camera::Camera;
+pub use light::{Light, LightKind, ShadowQuality};
+pub use material::{PbrInput, PbrMaterial};
+pub use size::Size;
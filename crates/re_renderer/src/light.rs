@@ -0,0 +1,132 @@
+use glam::{Mat4, Vec3};
+
+/// A single light illuminating the scene, with its shadow-mapping settings.
+///
+/// Passed to `SolidMeshDrawable::draw`, which uses only the first light it is given: it renders
+/// that light's shadow map before its color pass (drawing no casters into it if `shadow` is
+/// [`ShadowQuality::Disabled`]), and every shadow-receiving drawable (e.g. solid `Box3D`
+/// surfaces) samples it via `sample_shadow` in `shader/shadow.wgsl`. No scene or blueprint
+/// property sets it.
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub shadow: ShadowQuality,
+    /// Constant depth-bias added before the shadow-map compare, to fight shadow acne.
+    pub depth_bias: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            kind: LightKind::Directional {
+                direction: Vec3::new(-0.3, -1.0, -0.3).normalize(),
+            },
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            shadow: ShadowQuality::PcfPoisson {
+                samples: 16,
+                filter_radius: 3.0,
+            },
+            depth_bias: 0.005,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LightKind {
+    Directional { direction: Vec3 },
+    Point { position: Vec3 },
+}
+
+/// A world-space axis-aligned box, just big enough for [`Light::view_proj`] to fit a shadow map's
+/// frustum around whatever's currently in the scene (e.g. every shadow-casting batch's vertices).
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    pub const EMPTY: Self = Self {
+        min: Vec3::splat(f32::INFINITY),
+        max: Vec3::splat(f32::NEG_INFINITY),
+    };
+
+    #[must_use]
+    pub fn including(mut self, point: Vec3) -> Self {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+        self
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half the diagonal of the box, a safe radius for fitting a shadow frustum around it.
+    fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+}
+
+impl Light {
+    /// The light-space view-projection ("clip-from-world") matrix used to render this light's
+    /// shadow map and to sample it back in `shader/shadow.wgsl`, fit around `scene_bounds` (the
+    /// world-space bounds of every shadow-casting drawable this frame).
+    pub fn view_proj(&self, scene_bounds: BoundingBox) -> Mat4 {
+        let center = scene_bounds.center();
+        // A degenerate (zero-size, e.g. single-point) scene still needs a nonzero frustum to
+        // build a valid matrix.
+        let radius = scene_bounds.radius().max(1.0);
+
+        match self.kind {
+            LightKind::Directional { direction } => {
+                let eye = center - direction.normalize() * radius * 2.0;
+                let view = Mat4::look_at_rh(eye, center, Self::up_hint(direction));
+                let proj = Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.01, radius * 4.0);
+                proj * view
+            }
+            LightKind::Point { position } => {
+                let view = Mat4::look_at_rh(position, center, Self::up_hint(center - position));
+                let proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.01, radius * 4.0 + 0.01);
+                proj * view
+            }
+        }
+    }
+
+    /// An up vector that isn't parallel to `forward`, for building a look-at matrix.
+    fn up_hint(forward: Vec3) -> Vec3 {
+        if forward.normalize().dot(Vec3::Y).abs() > 0.99 {
+            Vec3::X
+        } else {
+            Vec3::Y
+        }
+    }
+}
+
+/// How a light's shadow map is filtered when sampled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowQuality {
+    /// No shadow map is rendered for this light at all.
+    Disabled,
+
+    /// Hardware 2x2 PCF via a depth-compare sampler's built-in bilinear filtering. Cheapest
+    /// option that still softens shadow-map aliasing.
+    HardwarePcf2x2,
+
+    /// `samples` taps on a precomputed Poisson disk, scaled by `filter_radius` (in shadow-map
+    /// texels), averaged into a soft visibility factor.
+    PcfPoisson { samples: u32, filter_radius: f32 },
+
+    /// Contact-hardening soft shadows: a blocker search over `search_radius` texels estimates
+    /// penumbra width, which scales the following Poisson-disk PCF pass's filter radius (capped
+    /// at `max_filter_radius`), so shadows sharpen near the occluder and blur with distance.
+    Pcss {
+        search_radius: f32,
+        light_size: f32,
+        max_filter_radius: f32,
+        samples: u32,
+    },
+}
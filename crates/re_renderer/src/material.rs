@@ -0,0 +1,41 @@
+/// CPU-side PBR shading parameters for a solid-mesh batch.
+///
+/// Base color is *not* part of this struct: it comes from each vertex's color attribute (the
+/// same annotation/`ColorRGBA` color used for wireframe rendering), so a single batch can mix
+/// differently-colored instances. `metallic`/`roughness`/`opacity` are constant for the batch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PbrMaterial {
+    /// 0 = dielectric (plastic-like), 1 = metal.
+    pub metallic: f32,
+
+    /// 0 = mirror-smooth, 1 = fully matte.
+    pub roughness: f32,
+
+    /// 1 = fully opaque.
+    pub opacity: f32,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        Self {
+            metallic: 0.0,
+            roughness: 0.8,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Per-fragment input to the `pbr()` lighting function in `shader/pbr.wgsl`.
+///
+/// Assembled by the solid-mesh fragment shader from interpolated vertex attributes (world
+/// position, world normal, vertex color) plus the batch's [`PbrMaterial`]. Kept as a plain,
+/// `bytemuck`-able struct so its layout matches the `PbrInput` WGSL struct field-for-field.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PbrInput {
+    pub world_position: [f32; 3],
+    pub metallic: f32,
+    pub world_normal: [f32; 3],
+    pub roughness: f32,
+    pub base_color: [f32; 4],
+}
@@ -0,0 +1,391 @@
+use glam::{Mat4, Quat, Vec3};
+use wgpu::util::DeviceExt as _;
+
+use re_data_store::InstanceIdHash;
+
+use crate::camera::Camera;
+use crate::Size;
+
+/// `Size::AUTO` doesn't have a finite scene-unit value, so it is stored as a negative radius.
+/// `shader/box_instanced.wgsl` does not read the stored radius: every edge is drawn as a
+/// hardware line-list line, whatever its radius.
+fn encode_radius(radius: Size) -> f32 {
+    radius.scene_units().unwrap_or(-1.0)
+}
+
+fn pack_color(color: egui::Color32) -> u32 {
+    let [r, g, b, a] = color.to_array();
+    (r as u32) << 24 | (g as u32) << 16 | (b as u32) << 8 | a as u32
+}
+
+/// One wireframe-box instance record, uploaded verbatim into a GPU storage buffer. The vertex
+/// shader (`shader/box_instanced.wgsl`) expands the 12 unit-cube edges for instance `i` by
+/// indexing `instances[i]` with `instance_index` / `vertex_index % 24`, so the 8-corner
+/// transform and 12-segment expansion this replaces never happens on the CPU.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BoxInstance {
+    half_size: [f32; 3],
+    radius: f32,
+    rotation: [f32; 4],
+    translation: [f32; 3],
+    color: u32,
+    /// Low/high 32 bits of the instance's [`InstanceIdHash`], read back for picking/hover.
+    picking_id: [u32; 2],
+    /// Pads the record to the 64-byte array stride of the WGSL `BoxInstance`.
+    _padding: [u32; 2],
+}
+
+/// A batch of box-instance records sharing one `world_from_obj` transform, uploaded by
+/// [`BoxInstanceDrawable::draw`] into a GPU storage buffer of its own.
+pub struct BoxInstanceBatch<'a> {
+    drawable: &'a mut BoxInstanceDrawable,
+    world_from_obj: Mat4,
+    instances: Vec<BoxInstance>,
+}
+
+impl<'a> BoxInstanceBatch<'a> {
+    fn new(drawable: &'a mut BoxInstanceDrawable, _name: &'static str) -> Self {
+        Self {
+            drawable,
+            world_from_obj: Mat4::IDENTITY,
+            instances: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn world_from_obj(mut self, world_from_obj: Mat4) -> Self {
+        self.world_from_obj = world_from_obj;
+        self
+    }
+
+    /// Push one box instance. `half_size`/`rotation`/`translation` are in object space; the
+    /// vertex shader composes them with `world_from_obj` itself rather than having the CPU
+    /// pre-transform each of the 8 corners.
+    pub fn add_box(&mut self, half_size: Vec3, rotation: Quat, translation: Vec3) -> BoxInstanceBuilder<'_, 'a> {
+        self.instances.push(BoxInstance {
+            half_size: half_size.into(),
+            radius: encode_radius(Size::AUTO),
+            rotation: rotation.into(),
+            translation: translation.into(),
+            color: pack_color(egui::Color32::WHITE),
+            picking_id: [0, 0],
+            _padding: [0, 0],
+        });
+        BoxInstanceBuilder {
+            batch: self,
+            index: self.instances.len() - 1,
+        }
+    }
+}
+
+impl Drop for BoxInstanceBatch<'_> {
+    fn drop(&mut self) {
+        self.drawable
+            .push_batch(self.world_from_obj, std::mem::take(&mut self.instances));
+    }
+}
+
+/// Builder for the instance record just pushed by [`BoxInstanceBatch::add_box`].
+pub struct BoxInstanceBuilder<'a, 'b> {
+    batch: &'a mut BoxInstanceBatch<'b>,
+    index: usize,
+}
+
+impl BoxInstanceBuilder<'_, '_> {
+    #[must_use]
+    pub fn radius(self, radius: Size) -> Self {
+        self.batch.instances[self.index].radius = encode_radius(radius);
+        self
+    }
+
+    #[must_use]
+    pub fn color(self, color: egui::Color32) -> Self {
+        self.batch.instances[self.index].color = pack_color(color);
+        self
+    }
+
+    pub fn user_data(self, instance_hash: InstanceIdHash) {
+        let packed = instance_hash.hash64();
+        self.batch.instances[self.index].picking_id =
+            [(packed & 0xffff_ffff) as u32, (packed >> 32) as u32];
+    }
+}
+
+struct BoxInstanceBatchData {
+    world_from_obj: Mat4,
+    instances: Vec<BoxInstance>,
+}
+
+/// Per-batch uniform read by `shader/box_instanced.wgsl`'s `vs_main`: just the shared transform,
+/// since every other per-instance field already lives in the `instances` storage buffer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BatchUniform {
+    world_from_obj: [[f32; 4]; 4],
+}
+
+fn batch_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("box_instanced_batch_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+fn instances_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("box_instanced_instances_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// GPU resources for drawing box-instance batches with `shader/box_instanced.wgsl`'s
+/// `vs_main`/`fs_main`. Built lazily on the first [`BoxInstanceDrawable::draw`] call, once the
+/// target formats are known.
+struct BoxInstancePipeline {
+    pipeline: wgpu::RenderPipeline,
+    instances_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    batch_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl BoxInstancePipeline {
+    fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        picking_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("box_instanced_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shader/box_instanced.wgsl").into()),
+        });
+
+        let instances_bind_group_layout = instances_bind_group_layout(device);
+        let camera_bind_group_layout = Camera::bind_group_layout(device);
+        let batch_bind_group_layout = batch_bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("box_instanced_pipeline_layout"),
+            bind_group_layouts: &[
+                &instances_bind_group_layout,
+                &camera_bind_group_layout,
+                &batch_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("box_instanced_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: color_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: picking_format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            instances_bind_group_layout,
+            camera_bind_group_layout,
+            batch_bind_group_layout,
+        }
+    }
+}
+
+/// Collects every wireframe-box batch submitted this frame and owns the instanced draw pipeline
+/// that renders them; each batch's storage buffer is created in [`BoxInstanceDrawable::draw`].
+#[derive(Default)]
+pub struct BoxInstanceDrawable {
+    pending: Vec<BoxInstanceBatchData>,
+    pipeline: Option<BoxInstancePipeline>,
+}
+
+impl BoxInstanceDrawable {
+    pub fn batch(&mut self, name: &'static str) -> BoxInstanceBatch<'_> {
+        BoxInstanceBatch::new(self, name)
+    }
+
+    fn push_batch(&mut self, world_from_obj: Mat4, instances: Vec<BoxInstance>) {
+        if instances.is_empty() {
+            return;
+        }
+        self.pending.push(BoxInstanceBatchData {
+            world_from_obj,
+            instances,
+        });
+    }
+
+    /// Total number of box instances queued for this frame, across every batch. Each one
+    /// contributes a single storage-buffer record instead of 12 CPU-materialized segments.
+    pub fn num_instances(&self) -> usize {
+        self.pending.iter().map(|batch| batch.instances.len()).sum()
+    }
+
+    /// Upload every pending batch's storage buffer and draw it as 24 line-list vertices per
+    /// instance (see `shader/box_instanced.wgsl`), into `color_target`, writing each instance's
+    /// picking id into `picking_target`. Consumes `self.pending`.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        color_target: &wgpu::TextureView,
+        picking_target: &wgpu::TextureView,
+        depth_target: &wgpu::TextureView,
+        camera: &Camera,
+    ) {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            return;
+        }
+
+        let pipeline = self.pipeline.get_or_insert_with(|| {
+            BoxInstancePipeline::new(
+                device,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                wgpu::TextureFormat::Rg32Uint,
+                wgpu::TextureFormat::Depth32Float,
+            )
+        });
+        let camera_bind_group = camera.create_bind_group(device, &pipeline.camera_bind_group_layout);
+
+        struct BatchResources {
+            instances_bind_group: wgpu::BindGroup,
+            batch_bind_group: wgpu::BindGroup,
+            instance_count: u32,
+        }
+
+        // Built before the render pass (rather than inside its loop) so every buffer/bind group
+        // outlives the `'pass`-scoped `render_pass` that borrows them below.
+        let batch_resources: Vec<BatchResources> = pending
+            .iter()
+            .map(|batch| {
+                let instances_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("box_instanced_instances_buffer"),
+                    contents: bytemuck::cast_slice(&batch.instances),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+                let instances_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("box_instanced_instances_bind_group"),
+                    layout: &pipeline.instances_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: instances_buffer.as_entire_binding(),
+                    }],
+                });
+
+                let batch_uniform = BatchUniform {
+                    world_from_obj: batch.world_from_obj.to_cols_array_2d(),
+                };
+                let batch_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("box_instanced_batch_uniform"),
+                    contents: bytemuck::bytes_of(&batch_uniform),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let batch_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("box_instanced_batch_bind_group"),
+                    layout: &pipeline.batch_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: batch_buffer.as_entire_binding(),
+                    }],
+                });
+
+                BatchResources {
+                    instances_bind_group,
+                    batch_bind_group,
+                    instance_count: batch.instances.len() as u32,
+                }
+            })
+            .collect();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("box_instanced_pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: color_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: picking_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+            ],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_target,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(1, &camera_bind_group, &[]);
+        for resources in &batch_resources {
+            render_pass.set_bind_group(0, &resources.instances_bind_group, &[]);
+            render_pass.set_bind_group(2, &resources.batch_bind_group, &[]);
+            render_pass.draw(0..24, 0..resources.instance_count);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod box_instanced;
+mod solid_mesh;
+
+pub use box_instanced::{BoxInstanceBatch, BoxInstanceDrawable};
+pub use solid_mesh::{SolidMeshBatch, SolidMeshDrawable};
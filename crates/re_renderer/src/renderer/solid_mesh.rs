@@ -0,0 +1,594 @@
+use glam::{Mat4, Vec3};
+use wgpu::util::DeviceExt as _;
+
+use re_data_store::InstanceIdHash;
+
+use crate::camera::Camera;
+use crate::light::Light;
+use crate::material::PbrMaterial;
+use crate::shadow::{self, ShadowCasterPipeline, ShadowMap};
+
+/// One shaded triangle vertex: object-space position and normal (the vertex shader applies the
+/// batch's `world_from_obj`), vertex color, and packed picking id.
+#[derive(Clone, Copy, Debug)]
+struct SolidVertex {
+    position: Vec3,
+    normal: Vec3,
+    color: egui::Color32,
+    /// Low/high 32 bits of the owning instance's [`InstanceIdHash`], read back for picking/hover
+    /// the same way [`crate::renderer::box_instanced::BoxInstance::picking_id`] is.
+    picking_id: [u32; 2],
+}
+
+/// [`SolidVertex`], laid out the way `shader/pbr.wgsl`'s `VertexInput` expects it on the GPU.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SolidGpuVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 4],
+    picking_id: [u32; 2],
+}
+
+impl From<SolidVertex> for SolidGpuVertex {
+    fn from(vertex: SolidVertex) -> Self {
+        let [r, g, b, a] = vertex.color.to_array();
+        Self {
+            position: vertex.position.into(),
+            normal: vertex.normal.into(),
+            color: [
+                r as f32 / 255.0,
+                g as f32 / 255.0,
+                b as f32 / 255.0,
+                a as f32 / 255.0,
+            ],
+            picking_id: vertex.picking_id,
+        }
+    }
+}
+
+const GPU_VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+    0 => Float32x3, // position
+    1 => Float32x3, // normal
+    2 => Float32x4, // color
+    3 => Uint32x2,  // picking_id
+];
+
+fn gpu_vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<SolidGpuVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &GPU_VERTEX_ATTRIBUTES,
+    }
+}
+
+const SHADOW_CASTER_VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![
+    0 => Float32x3, // position
+];
+
+/// Same vertex buffer as [`gpu_vertex_buffer_layout`], but only the `position` attribute the
+/// depth-only shadow-caster pipeline (`shader/shadow_caster.wgsl`) reads.
+fn shadow_caster_vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<SolidGpuVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &SHADOW_CASTER_VERTEX_ATTRIBUTES,
+    }
+}
+
+/// Per-batch uniform read by `shader/pbr.wgsl`'s `fs_main`/`vs_main`: the batch's transform and
+/// material, plus whether it should sample the active shadow map at all.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BatchUniform {
+    world_from_obj: [[f32; 4]; 4],
+    normal_from_obj: [[f32; 4]; 4],
+    metallic: f32,
+    roughness: f32,
+    opacity: f32,
+    receives_shadows: u32,
+}
+
+fn batch_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("solid_mesh_batch_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// A CPU-side batch of shaded triangles (e.g. the solid surface of a filled `Box3D`) sharing one
+/// `world_from_obj` transform and one [`PbrMaterial`]. Uploaded to the GPU as a single vertex
+/// buffer and drawn with `pbr()` (see `shader/pbr.wgsl`).
+pub struct SolidMeshBatch<'a> {
+    drawable: &'a mut SolidMeshDrawable,
+    world_from_obj: Mat4,
+    material: PbrMaterial,
+    casts_shadows: bool,
+    receives_shadows: bool,
+    vertices: Vec<SolidVertex>,
+}
+
+impl<'a> SolidMeshBatch<'a> {
+    fn new(drawable: &'a mut SolidMeshDrawable, _name: &'static str) -> Self {
+        Self {
+            drawable,
+            world_from_obj: Mat4::IDENTITY,
+            material: PbrMaterial::default(),
+            casts_shadows: false,
+            receives_shadows: false,
+            vertices: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn world_from_obj(mut self, world_from_obj: Mat4) -> Self {
+        self.world_from_obj = world_from_obj;
+        self
+    }
+
+    #[must_use]
+    pub fn material(mut self, material: PbrMaterial) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Whether this batch is drawn into the shadow map of the first light passed to
+    /// [`SolidMeshDrawable::draw`] (so other geometry can be occluded by it). Ignored when that
+    /// light's [`crate::light::ShadowQuality`] is `Disabled`.
+    #[must_use]
+    pub fn casts_shadows(mut self, casts_shadows: bool) -> Self {
+        self.casts_shadows = casts_shadows;
+        self
+    }
+
+    /// Whether this batch's fragments sample that light's shadow map (via `sample_shadow` in
+    /// `shader/shadow.wgsl`) when shading with `pbr()`, rather than assuming full visibility.
+    #[must_use]
+    pub fn receives_shadows(mut self, receives_shadows: bool) -> Self {
+        self.receives_shadows = receives_shadows;
+        self
+    }
+
+    /// Push one object-space triangle (the batch's `world_from_obj` is applied on the GPU),
+    /// given as three `(position, normal)` vertices.
+    pub fn add_triangle(&mut self, triangle: [(Vec3, Vec3); 3]) -> SolidTriangleBuilder<'_, 'a> {
+        let first_vertex = self.vertices.len();
+        for (position, normal) in triangle {
+            self.vertices.push(SolidVertex {
+                position,
+                normal,
+                color: egui::Color32::WHITE,
+                picking_id: [0, 0],
+            });
+        }
+        SolidTriangleBuilder {
+            batch: self,
+            first_vertex,
+        }
+    }
+}
+
+impl Drop for SolidMeshBatch<'_> {
+    fn drop(&mut self) {
+        self.drawable.push_batch(
+            self.world_from_obj,
+            self.material,
+            self.casts_shadows,
+            self.receives_shadows,
+            std::mem::take(&mut self.vertices),
+        );
+    }
+}
+
+/// Builder for the three vertices just pushed by [`SolidMeshBatch::add_triangle`].
+pub struct SolidTriangleBuilder<'a, 'b> {
+    batch: &'a mut SolidMeshBatch<'b>,
+    first_vertex: usize,
+}
+
+impl SolidTriangleBuilder<'_, '_> {
+    #[must_use]
+    pub fn color(self, color: egui::Color32) -> Self {
+        for vertex in &mut self.batch.vertices[self.first_vertex..] {
+            vertex.color = color;
+        }
+        self
+    }
+
+    /// Attach the logical instance this triangle belongs to, for picking/hover: written into
+    /// every vertex's `picking_id`, uploaded alongside `position`/`normal`/`color`, and output by
+    /// `fs_main` to the picking-id render target the same way
+    /// [`crate::renderer::box_instanced::BoxInstanceBuilder::user_data`] does for wireframe boxes.
+    pub fn user_data(self, instance_hash: InstanceIdHash) {
+        let packed = instance_hash.hash64();
+        let picking_id = [(packed & 0xffff_ffff) as u32, (packed >> 32) as u32];
+        for vertex in &mut self.batch.vertices[self.first_vertex..] {
+            vertex.picking_id = picking_id;
+        }
+    }
+}
+
+struct SolidMeshInstance {
+    world_from_obj: Mat4,
+    material: PbrMaterial,
+    casts_shadows: bool,
+    receives_shadows: bool,
+    vertices: Vec<SolidVertex>,
+}
+
+/// GPU resources for drawing solid-mesh batches with `shader/pbr.wgsl`'s `vs_main`/`fs_main`.
+/// Built lazily on the first [`SolidMeshDrawable::draw`] call, once the target formats are known.
+struct SolidMeshPipeline {
+    pipeline: wgpu::RenderPipeline,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    batch_bind_group_layout: wgpu::BindGroupLayout,
+    light_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_caster: ShadowCasterPipeline,
+}
+
+impl SolidMeshPipeline {
+    fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        picking_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+    ) -> Self {
+        // `pbr.wgsl` `#import`s `sample_shadow` from `shadow.wgsl`; wgpu's WGSL front-end has no
+        // import support, so both files are joined here with their import directives stripped.
+        let source = format!(
+            "{}\n{}",
+            include_str!("../shader/shadow.wgsl").replacen("#define_import_path re_renderer::shadow", "", 1),
+            include_str!("../shader/pbr.wgsl").replacen("#import re_renderer::shadow::sample_shadow", "", 1),
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("pbr_shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let camera_bind_group_layout = Camera::bind_group_layout(device);
+        let batch_bind_group_layout = batch_bind_group_layout(device);
+        let light_bind_group_layout = shadow::light_bind_group_layout(device);
+        let shadow_bind_group_layout = ShadowMap::bind_group_layout(device);
+        let shadow_caster = ShadowCasterPipeline::new(device, shadow_caster_vertex_buffer_layout());
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pbr_pipeline_layout"),
+            bind_group_layouts: &[
+                &camera_bind_group_layout,
+                &batch_bind_group_layout,
+                &light_bind_group_layout,
+                &shadow_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("pbr_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[gpu_vertex_buffer_layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: color_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: picking_format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            camera_bind_group_layout,
+            batch_bind_group_layout,
+            light_bind_group_layout,
+            shadow_bind_group_layout,
+            shadow_caster,
+        }
+    }
+}
+
+/// Owns the GPU-side vertex buffer and `pbr()` render pipeline used to draw every solid-mesh
+/// batch submitted this frame (e.g. all filled `Box3D`s).
+#[derive(Default)]
+pub struct SolidMeshDrawable {
+    pending: Vec<SolidMeshInstance>,
+    pipeline: Option<SolidMeshPipeline>,
+}
+
+impl SolidMeshDrawable {
+    pub fn batch(&mut self, name: &'static str) -> SolidMeshBatch<'_> {
+        SolidMeshBatch::new(self, name)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_batch(
+        &mut self,
+        world_from_obj: Mat4,
+        material: PbrMaterial,
+        casts_shadows: bool,
+        receives_shadows: bool,
+        vertices: Vec<SolidVertex>,
+    ) {
+        if vertices.is_empty() {
+            return;
+        }
+        self.pending.push(SolidMeshInstance {
+            world_from_obj,
+            material,
+            casts_shadows,
+            receives_shadows,
+            vertices,
+        });
+    }
+
+    /// Total number of triangles queued for this frame, across every batch.
+    pub fn num_triangles(&self) -> usize {
+        self.pending.iter().map(|instance| instance.vertices.len() / 3).sum()
+    }
+
+    /// Upload every pending batch, render `lights[0]`'s shadow map from every batch flagged
+    /// `casts_shadows`, then draw every batch with `pbr()` shading (sampling that shadow map for
+    /// batches flagged `receives_shadows`) into `color_target`, writing each vertex's picking id
+    /// into `picking_target`. Consumes `self.pending`.
+    ///
+    /// Only the first light is rendered: `pbr.wgsl`'s `fs_main` takes a single `LightUniform`, so
+    /// multi-light support needs either multiple draw passes (one per light, additively blended)
+    /// or an array uniform — tracked as follow-up, not attempted here. An empty `lights` shades
+    /// every batch under `Light::default()` and draws no casters into the shadow map, so
+    /// sampling it gives full visibility.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        color_target: &wgpu::TextureView,
+        picking_target: &wgpu::TextureView,
+        depth_target: &wgpu::TextureView,
+        camera: &Camera,
+        lights: &[Light],
+    ) {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            return;
+        }
+
+        let pipeline = self.pipeline.get_or_insert_with(|| {
+            SolidMeshPipeline::new(
+                device,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                wgpu::TextureFormat::Rg32Uint,
+                wgpu::TextureFormat::Depth32Float,
+            )
+        });
+        let camera_bind_group = camera.create_bind_group(device, &pipeline.camera_bind_group_layout);
+
+        let light = lights.first().copied().unwrap_or_default();
+        let scene_bounds = pending
+            .iter()
+            .flat_map(|instance| {
+                instance
+                    .vertices
+                    .iter()
+                    .map(|vertex| instance.world_from_obj.transform_point3(vertex.position))
+            })
+            .fold(crate::light::BoundingBox::EMPTY, crate::light::BoundingBox::including);
+        let light_clip_from_world = light.view_proj(scene_bounds);
+
+        let shadow_map = ShadowMap::new(device, light);
+
+        struct ShadowCasterResources {
+            vertex_buffer: wgpu::Buffer,
+            bind_group: wgpu::BindGroup,
+            vertex_count: u32,
+        }
+
+        // Built before `shadow_pass` (rather than inside its loop) for the same reason as
+        // `batch_resources` below: every buffer/bind group must outlive the `'pass`-scoped
+        // `shadow_pass` that borrows them, not just the loop iteration that creates them.
+        let shadow_caster_resources: Vec<ShadowCasterResources> = pending
+            .iter()
+            .filter(|instance| instance.casts_shadows)
+            .map(|instance| {
+                let gpu_vertices: Vec<SolidGpuVertex> =
+                    instance.vertices.iter().copied().map(SolidGpuVertex::from).collect();
+                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("shadow_caster_vertex_buffer"),
+                    contents: bytemuck::cast_slice(&gpu_vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                let batch_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("shadow_caster_batch_uniform"),
+                    contents: bytemuck::bytes_of(&instance.world_from_obj.to_cols_array_2d()),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("shadow_caster_batch_bind_group"),
+                    layout: pipeline.shadow_caster.batch_bind_group_layout(),
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: batch_buffer.as_entire_binding(),
+                    }],
+                });
+
+                ShadowCasterResources {
+                    vertex_buffer,
+                    bind_group,
+                    vertex_count: gpu_vertices.len() as u32,
+                }
+            })
+            .collect();
+
+        let light_clip_from_world_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shadow_caster_light_uniform"),
+            contents: bytemuck::bytes_of(&light_clip_from_world.to_cols_array_2d()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let shadow_caster_light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("shadow_caster_light_bind_group"),
+            layout: pipeline.shadow_caster.light_bind_group_layout(),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_clip_from_world_buffer.as_entire_binding(),
+            }],
+        });
+
+        {
+            let mut shadow_pass = shadow_map.begin_render_pass(encoder);
+            if shadow::renders_shadow_map(light.shadow) && !lights.is_empty() {
+                shadow_pass.set_pipeline(pipeline.shadow_caster.pipeline());
+                shadow_pass.set_bind_group(0, &shadow_caster_light_bind_group, &[]);
+
+                for resources in &shadow_caster_resources {
+                    shadow_pass.set_bind_group(1, &resources.bind_group, &[]);
+                    shadow_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+                    shadow_pass.draw(0..resources.vertex_count, 0..1);
+                }
+            }
+            // Dropping `shadow_pass` here (quality `Disabled`, or no casters) still submits the
+            // `LoadOp::Clear(1.0)` from `begin_render_pass`, leaving the map at "nothing occludes
+            // anything" — `sample_shadow` then naturally returns full visibility either way.
+        }
+
+        let light_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("pbr_light_uniform"),
+            contents: bytemuck::bytes_of(&light.uniform(light_clip_from_world)),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pbr_light_bind_group"),
+            layout: &pipeline.light_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let shadow_bind_group = shadow_map.create_bind_group(device, &pipeline.shadow_bind_group_layout);
+
+        struct BatchResources {
+            vertex_buffer: wgpu::Buffer,
+            bind_group: wgpu::BindGroup,
+            vertex_count: u32,
+        }
+
+        // Built before the render pass (rather than inside its loop) so every buffer/bind group
+        // outlives the `'pass`-scoped `render_pass` that borrows them below.
+        let batch_resources: Vec<BatchResources> = pending
+            .iter()
+            .map(|instance| {
+                let gpu_vertices: Vec<SolidGpuVertex> =
+                    instance.vertices.iter().copied().map(SolidGpuVertex::from).collect();
+                let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("solid_mesh_vertex_buffer"),
+                    contents: bytemuck::cast_slice(&gpu_vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+
+                let normal_from_obj = instance.world_from_obj.inverse().transpose();
+                let batch_uniform = BatchUniform {
+                    world_from_obj: instance.world_from_obj.to_cols_array_2d(),
+                    normal_from_obj: normal_from_obj.to_cols_array_2d(),
+                    metallic: instance.material.metallic,
+                    roughness: instance.material.roughness,
+                    opacity: instance.material.opacity,
+                    receives_shadows: instance.receives_shadows as u32,
+                };
+                let batch_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("solid_mesh_batch_uniform"),
+                    contents: bytemuck::bytes_of(&batch_uniform),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("solid_mesh_batch_bind_group"),
+                    layout: &pipeline.batch_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: batch_buffer.as_entire_binding(),
+                    }],
+                });
+
+                BatchResources {
+                    vertex_buffer,
+                    bind_group,
+                    vertex_count: gpu_vertices.len() as u32,
+                }
+            })
+            .collect();
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("solid_mesh_pass"),
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: color_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+                Some(wgpu::RenderPassColorAttachment {
+                    view: picking_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                }),
+            ],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_target,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, &camera_bind_group, &[]);
+        render_pass.set_bind_group(2, &light_bind_group, &[]);
+        render_pass.set_bind_group(3, &shadow_bind_group, &[]);
+        for resources in &batch_resources {
+            render_pass.set_bind_group(1, &resources.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+            render_pass.draw(0..resources.vertex_count, 0..1);
+        }
+    }
+}
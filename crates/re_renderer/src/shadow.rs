@@ -0,0 +1,418 @@
+use glam::Mat4;
+use wgpu::util::DeviceExt as _;
+
+use crate::light::{Light, LightKind, ShadowQuality};
+
+/// A fixed, precomputed Poisson disk of 16 points in `[-1, 1]^2`, used by both the PCF and PCSS
+/// passes in `shader/shadow.wgsl` (scaled by the filter/search radius at sample time). Generated
+/// once offline rather than at runtime so every frame samples the same, well-distributed pattern.
+pub const POISSON_DISK_16: [[f32; 2]; 16] = [
+    [-0.94201624, -0.39906216],
+    [0.94558609, -0.76890725],
+    [-0.094184101, -0.92938870],
+    [0.34495938, 0.29387760],
+    [-0.91588581, 0.45771432],
+    [-0.81544232, -0.87912464],
+    [-0.38277543, 0.27676845],
+    [0.97484398, 0.75648379],
+    [0.44323325, -0.97511554],
+    [0.53742981, -0.47373420],
+    [-0.26496911, -0.41893023],
+    [0.79197514, 0.19090188],
+    [-0.24188840, 0.99706507],
+    [-0.81409955, 0.91437590],
+    [0.19984126, 0.78641367],
+    [0.14383161, -0.14100790],
+];
+
+/// Render-target size (in texels, per side) for a directional/point light's shadow map.
+/// Higher than this starts costing noticeably more to render and filter; lower starts
+/// showing blocky shadow-map aliasing even with PCF.
+pub const SHADOW_MAP_RESOLUTION: u32 = 2048;
+
+pub const SHADOW_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Owns one light's shadow-map depth texture and the samplers and Poisson-disk buffer used to
+/// read it back. Scene depth is rendered into it by a [`ShadowCasterPipeline`].
+pub struct ShadowMap {
+    pub light: Light,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    compare_sampler: wgpu::Sampler,
+    /// Same `depth_texture`, read back without a depth-compare (used by `blocker_search` in
+    /// `shader/shadow.wgsl`, which needs the raw stored depth rather than a pass/fail result).
+    raw_sampler: wgpu::Sampler,
+    poisson_disk_buffer: wgpu::Buffer,
+}
+
+impl ShadowMap {
+    pub fn new(device: &wgpu::Device, light: Light) -> Self {
+        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("shadow_map_depth"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_RESOLUTION,
+                height: SHADOW_MAP_RESOLUTION,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_MAP_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // `CompareFunction::LessEqual` turns every texture sample into a pass/fail shadow test;
+        // with `Filtering` this gives hardware 2x2 PCF for free, which `ShadowQuality::PcfPoisson`
+        // and `Pcss` build on top of with additional Poisson-disk taps.
+        let compare_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("shadow_map_compare_sampler"),
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let raw_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("shadow_map_raw_sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let poisson_disk_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shadow_map_poisson_disk"),
+            contents: bytemuck::cast_slice(&POISSON_DISK_16),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        Self {
+            light,
+            depth_texture,
+            depth_view,
+            compare_sampler,
+            raw_sampler,
+            poisson_disk_buffer,
+        }
+    }
+
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
+    pub fn compare_sampler(&self) -> &wgpu::Sampler {
+        &self.compare_sampler
+    }
+
+    pub fn poisson_disk_buffer(&self) -> &wgpu::Buffer {
+        &self.poisson_disk_buffer
+    }
+
+    /// Begins the depth-only render pass that writes this light's shadow map. Every
+    /// shadow-casting drawable (solid boxes, meshes, ...) should be drawn into it with the
+    /// light-space clip matrix from [`Light::view_proj`], not the main camera's.
+    pub fn begin_render_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("shadow_map_pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+
+    /// Bind group layout matching `shader/shadow.wgsl`'s `@group(3)`: the depth texture (read
+    /// twice, once through a compare sampler for PCF and once raw for PCSS's blocker search),
+    /// the filter settings, and the Poisson disk.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shadow_map_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    /// Builds this frame's `@group(3)` bind group: `self`'s depth texture/samplers/Poisson disk,
+    /// plus a freshly uploaded [`ShadowParamsUniform`] for `self.light`'s current quality.
+    pub fn create_bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shadow_params_uniform"),
+            contents: bytemuck::bytes_of(&self.light.shadow_params_uniform()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("shadow_map_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.compare_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.poisson_disk_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&self.depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::Sampler(&self.raw_sampler),
+                },
+            ],
+        })
+    }
+}
+
+/// CPU-side mirror of `shader/shadow.wgsl`'s `ShadowParams`, built from a [`Light`]'s
+/// [`ShadowQuality`] and `depth_bias` by [`Light::shadow_params_uniform`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowParamsUniform {
+    quality: u32,
+    samples: u32,
+    filter_radius: f32,
+    search_radius: f32,
+    light_size: f32,
+    max_filter_radius: f32,
+    depth_bias: f32,
+    _padding: f32,
+}
+
+/// Light input to `pbr.wgsl`: `vs_main` reads the light-space clip matrix used to look its shadow
+/// map up, `fs_main` this light's direction/position, color, and intensity.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    light_clip_from_world: [[f32; 4]; 4],
+    direction_or_position: [f32; 3],
+    is_point: u32,
+    color: [f32; 3],
+    intensity: f32,
+}
+
+/// Bind group layout matching `shader/pbr.wgsl`'s `@group(2)`.
+pub fn light_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("light_bind_group_layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+impl Light {
+    fn shadow_params_uniform(&self) -> ShadowParamsUniform {
+        let (quality, samples, filter_radius, search_radius, light_size, max_filter_radius) = match self.shadow {
+            ShadowQuality::Disabled | ShadowQuality::HardwarePcf2x2 => (0, 1, 0.0, 0.0, 0.0, 0.0),
+            ShadowQuality::PcfPoisson { samples, filter_radius } => (1, samples, filter_radius, 0.0, 0.0, 0.0),
+            ShadowQuality::Pcss { search_radius, light_size, max_filter_radius, samples } => {
+                (2, samples, 0.0, search_radius, light_size, max_filter_radius)
+            }
+        };
+        ShadowParamsUniform {
+            quality,
+            samples,
+            filter_radius,
+            search_radius,
+            light_size,
+            max_filter_radius,
+            depth_bias: self.depth_bias,
+            _padding: 0.0,
+        }
+    }
+
+    /// Builds the uniform `pbr.wgsl`'s `vs_main`/`fs_main` read to shade under this light and sample its
+    /// shadow map, given the light-space clip matrix (see [`Light::view_proj`]) used to render it.
+    pub fn uniform(&self, light_clip_from_world: Mat4) -> LightUniform {
+        let (direction_or_position, is_point) = match self.kind {
+            LightKind::Directional { direction } => (direction, 0),
+            LightKind::Point { position } => (position, 1),
+        };
+        LightUniform {
+            light_clip_from_world: light_clip_from_world.to_cols_array_2d(),
+            direction_or_position: direction_or_position.into(),
+            is_point,
+            color: self.color,
+            intensity: self.intensity,
+        }
+    }
+}
+
+/// GPU resources for the depth-only pass that renders shadow-casting geometry into a
+/// [`ShadowMap`] from the light's point of view (`shader/shadow_caster.wgsl`).
+pub struct ShadowCasterPipeline {
+    pipeline: wgpu::RenderPipeline,
+    light_bind_group_layout: wgpu::BindGroupLayout,
+    batch_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ShadowCasterPipeline {
+    pub fn new(device: &wgpu::Device, vertex_buffer_layout: wgpu::VertexBufferLayout<'static>) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shadow_caster_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader/shadow_caster.wgsl").into()),
+        });
+
+        let light_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shadow_caster_light_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let batch_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shadow_caster_batch_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("shadow_caster_pipeline_layout"),
+            bind_group_layouts: &[&light_bind_group_layout, &batch_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("shadow_caster_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_buffer_layout],
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                // Unlike the main color pass, shadow casters are rendered unculled: a
+                // back-face-culled caster can still correctly occlude light reaching a receiver
+                // behind it, and skipping the cull avoids acne on thin geometry.
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_MAP_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            light_bind_group_layout,
+            batch_bind_group_layout,
+        }
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn light_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.light_bind_group_layout
+    }
+
+    pub fn batch_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.batch_bind_group_layout
+    }
+}
+
+/// Whether `quality` requires a shadow map to be rendered for its light at all.
+pub fn renders_shadow_map(quality: ShadowQuality) -> bool {
+    !matches!(quality, ShadowQuality::Disabled)
+}
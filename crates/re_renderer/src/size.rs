@@ -0,0 +1,25 @@
+/// The size (radius, half-width, ...) of a piece of scene geometry, either fixed in scene units
+/// or automatically derived by the renderer (e.g. "a couple of pixels, whatever that is in world
+/// space at the current viewport distance").
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Size(f32);
+
+impl Size {
+    /// Let the renderer pick a reasonable size automatically.
+    pub const AUTO: Self = Self(-1.0);
+
+    /// A fixed size in scene units.
+    pub fn new_scene(size_in_scene_units: f32) -> Self {
+        Self(size_in_scene_units.max(0.0))
+    }
+
+    /// Whether this is [`Size::AUTO`].
+    pub fn is_auto(self) -> bool {
+        self.0 < 0.0
+    }
+
+    /// The size in scene units, or `None` if this is [`Size::AUTO`].
+    pub fn scene_units(self) -> Option<f32> {
+        (!self.is_auto()).then_some(self.0)
+    }
+}
@@ -8,7 +8,10 @@ use re_log_types::{
     IndexHash, MsgId, ObjectType,
 };
 use re_query::{query_entity_with_primary, EntityView, QueryError};
-use re_renderer::Size;
+use re_renderer::{
+    renderer::{BoxInstanceBatch, SolidMeshBatch},
+    PbrMaterial, Size,
+};
 
 use crate::{
     misc::ViewerContext,
@@ -37,38 +40,113 @@ lazy_static::lazy_static! {
         vec3(0.5, 0.5, -0.5),
         vec3(0.5, 0.5, 0.5),
     ];
-}
 
-/// Create an iterator of line segments that build unit cube transformed by `transform`.
-fn transformed_box_segments(transform: glam::Affine3A) -> impl Iterator<Item = (Vec3, Vec3)> {
-    let corners = [
-        transform.transform_point3(UNIT_CUBE[0]),
-        transform.transform_point3(UNIT_CUBE[1]),
-        transform.transform_point3(UNIT_CUBE[2]),
-        transform.transform_point3(UNIT_CUBE[3]),
-        transform.transform_point3(UNIT_CUBE[4]),
-        transform.transform_point3(UNIT_CUBE[5]),
-        transform.transform_point3(UNIT_CUBE[6]),
-        transform.transform_point3(UNIT_CUBE[7]),
+    /// The six faces of [`UNIT_CUBE`], each as four corner indices (CCW as seen from *inside*
+    /// the box) together with the face's outward-facing local-space normal. Triangulation in
+    /// [`transformed_box_faces`] reverses this to wind CCW as seen from outside.
+    static ref UNIT_CUBE_FACES: [([usize; 4], Vec3); 6] = [
+        ([0b000, 0b010, 0b011, 0b001], vec3(-1.0, 0.0, 0.0)), // -X
+        ([0b100, 0b101, 0b111, 0b110], vec3(1.0, 0.0, 0.0)),  // +X
+        ([0b000, 0b001, 0b101, 0b100], vec3(0.0, -1.0, 0.0)), // -Y
+        ([0b010, 0b110, 0b111, 0b011], vec3(0.0, 1.0, 0.0)),  // +Y
+        ([0b000, 0b100, 0b110, 0b010], vec3(0.0, 0.0, -1.0)), // -Z
+        ([0b001, 0b011, 0b111, 0b101], vec3(0.0, 0.0, 1.0)),  // +Z
     ];
-    [
-        // bottom:
-        (corners[0b000], corners[0b001]),
-        (corners[0b000], corners[0b010]),
-        (corners[0b011], corners[0b001]),
-        (corners[0b011], corners[0b010]),
-        // top:
-        (corners[0b100], corners[0b101]),
-        (corners[0b100], corners[0b110]),
-        (corners[0b111], corners[0b101]),
-        (corners[0b111], corners[0b110]),
-        // sides:
-        (corners[0b000], corners[0b100]),
-        (corners[0b001], corners[0b101]),
-        (corners[0b010], corners[0b110]),
-        (corners[0b011], corners[0b111]),
-    ]
-    .into_iter()
+}
+
+/// How the surface of a logged `Box3D` should be drawn.
+///
+/// Set via the `fill_mode` object property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillMode {
+    /// Draw only the box edges, as a wireframe. This is the classic look.
+    Wireframe,
+    /// Draw the box as a shaded, opaque or translucent surface.
+    Solid,
+    /// Draw the shaded surface and overlay the wireframe on top of it.
+    SolidWireframe,
+}
+
+impl Default for FillMode {
+    fn default() -> Self {
+        Self::Wireframe
+    }
+}
+
+impl FillMode {
+    fn draw_wireframe(self) -> bool {
+        matches!(self, Self::Wireframe | Self::SolidWireframe)
+    }
+
+    fn draw_solid(self) -> bool {
+        matches!(self, Self::Solid | Self::SolidWireframe)
+    }
+}
+
+/// Triangulated, shaded faces of a unit cube transformed by `transform`, for solid/PBR rendering.
+///
+/// Each yielded triangle is three `(position, normal)` vertices in the object space `transform`
+/// maps into (the batch's `world_from_obj` is applied on the GPU), wound counter-clockwise as
+/// seen from outside the box.
+fn transformed_box_faces(
+    transform: glam::Affine3A,
+) -> impl Iterator<Item = [(Vec3, Vec3); 3]> + '_ {
+    let corners = UNIT_CUBE.map(|corner| transform.transform_point3(corner));
+    // The box may be non-uniformly scaled (distinct half-sizes per axis), so normals must go
+    // through the inverse-transpose of the linear part rather than the transform directly.
+    let normal_from_local = transform.matrix3.inverse().transpose();
+
+    UNIT_CUBE_FACES.iter().flat_map(move |&(corner_indices, local_normal)| {
+        let normal = normal_from_local.mul_vec3(local_normal).normalize();
+        let [a, b, c, d] = corner_indices.map(|i| corners[i]);
+        // `(a, b, c, d)` walk the face CCW as seen from *inside* the box (see the
+        // `UNIT_CUBE_FACES` winding test below), so the two triangles need `b`/`c` and `c`/`d`
+        // swapped to wind CCW as seen from outside, matching `local_normal`.
+        [
+            [(a, normal), (c, normal), (b, normal)],
+            [(a, normal), (d, normal), (c, normal)],
+        ]
+    })
+}
+
+/// Default PBR material for a solid-filled box: fully dielectric and fairly rough, so the
+/// shading reads as matte rather than mirror-like.
+fn default_box_material(fill_opacity: f32) -> PbrMaterial {
+    PbrMaterial {
+        opacity: fill_opacity,
+        metallic: 0.0,
+        roughness: 0.8,
+    }
+}
+
+/// Begin a wireframe-box batch. Each box is pushed as a compact record (half-size, rotation,
+/// translation, color, radius, instance hash) rather than a materialized set of line segments:
+/// the vertex shader expands the unit-cube edges per instance (see
+/// `re_renderer::renderer::box_instanced`), which is much cheaper than transforming and
+/// materializing 12 segments per box on the CPU.
+fn begin_box_batch(
+    box_instances: &mut re_renderer::renderer::BoxInstanceDrawable,
+    batch_name: &'static str,
+    world_from_obj: Mat4,
+) -> BoxInstanceBatch<'_> {
+    box_instances.batch(batch_name).world_from_obj(world_from_obj)
+}
+
+/// Begin a solid-mesh batch for a filled box's shaded surface. Solid boxes are opaque-ish
+/// geometry, so they both cast and receive shadows in the scene's light (see
+/// `re_renderer::shadow`).
+fn begin_solid_box_batch(
+    solid_meshes: &mut re_renderer::renderer::SolidMeshDrawable,
+    batch_name: &'static str,
+    world_from_obj: Mat4,
+    fill_opacity: f32,
+) -> SolidMeshBatch<'_> {
+    solid_meshes
+        .batch(batch_name)
+        .world_from_obj(world_from_obj)
+        .material(default_box_material(fill_opacity))
+        .casts_shadows(true)
+        .receives_shadows(true)
 }
 
 pub struct Boxes3DPartClassic;
@@ -92,14 +170,19 @@ impl ScenePart for Boxes3DPartClassic {
             let annotations = scene.annotation_map.find(obj_path);
             let default_color = DefaultColor::ObjPath(obj_path);
             let properties = query.obj_props.get(obj_path);
+            let fill_mode = properties.fill_mode;
+            let fill_opacity = properties.fill_opacity;
             let ReferenceFromObjTransform::Reachable(world_from_obj) = transforms.reference_from_obj(obj_path) else {
                 continue;
             };
-            let mut line_batch = scene
-                .primitives
-                .line_strips
-                .batch("box 3d")
-                .world_from_obj(world_from_obj);
+            let mut box_batch =
+                begin_box_batch(&mut scene.primitives.box_instances, "box 3d", world_from_obj);
+            let mut solid_batch = begin_solid_box_batch(
+                &mut scene.primitives.solid_meshes,
+                "box 3d solid",
+                world_from_obj,
+                fill_opacity,
+            );
 
             let visitor = |instance_index: Option<&IndexHash>,
                            _time: i64,
@@ -136,11 +219,26 @@ impl ScenePart for Boxes3DPartClassic {
                     Vec3::from(obb.translation),
                 );
 
-                line_batch
-                    .add_segments(transformed_box_segments(transform))
-                    .radius(line_radius)
-                    .color(color)
-                    .user_data(instance_hash);
+                if fill_mode.draw_solid() {
+                    for triangle in transformed_box_faces(transform) {
+                        solid_batch
+                            .add_triangle(triangle)
+                            .color(color)
+                            .user_data(instance_hash);
+                    }
+                }
+
+                if fill_mode.draw_wireframe() {
+                    box_batch
+                        .add_box(
+                            Vec3::from(obb.half_size),
+                            glam::Quat::from_array(obb.rotation),
+                            Vec3::from(obb.translation),
+                        )
+                        .radius(line_radius)
+                        .color(color)
+                        .user_data(instance_hash);
+                }
             };
 
             visit_type_data_4(
@@ -170,12 +268,16 @@ impl Boxes3DPart {
 
         let annotations = scene.annotation_map.find(ent_path);
         let default_color = DefaultColor::ObjPath(ent_path);
+        let fill_mode = props.fill_mode;
 
-        let mut line_batch = scene
-            .primitives
-            .line_strips
-            .batch("box 3d")
-            .world_from_obj(world_from_obj);
+        let mut box_batch =
+            begin_box_batch(&mut scene.primitives.box_instances, "box 3d", world_from_obj);
+        let mut solid_batch = begin_solid_box_batch(
+            &mut scene.primitives.solid_meshes,
+            "box 3d solid",
+            world_from_obj,
+            props.fill_opacity,
+        );
 
         let visitor = |instance: Instance,
                        half_size: Box3D,
@@ -211,11 +313,22 @@ impl Boxes3DPart {
             let tran = position.map_or(glam::Vec3::ZERO, glam::Vec3::from);
             let transform = glam::Affine3A::from_scale_rotation_translation(scale, rot, tran);
 
-            line_batch
-                .add_segments(transformed_box_segments(transform))
-                .radius(radius)
-                .color(color)
-                .user_data(instance_hash);
+            if fill_mode.draw_solid() {
+                for triangle in transformed_box_faces(transform) {
+                    solid_batch
+                        .add_triangle(triangle)
+                        .color(color)
+                        .user_data(instance_hash);
+                }
+            }
+
+            if fill_mode.draw_wireframe() {
+                box_batch
+                    .add_box(scale, rot, tran)
+                    .radius(radius)
+                    .color(color)
+                    .user_data(instance_hash);
+            }
 
             if let Some(label) = annotation_info.label(label.as_ref().map(|s| &s.0)) {
                 scene.ui.labels_3d.push(Label3D {
@@ -278,3 +391,47 @@ impl ScenePart for Boxes3DPart {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// [`UNIT_CUBE_FACES`]' raw corner order is documented as CCW *as seen from inside* the box,
+    /// which is exactly the opposite winding of its declared outward-facing `local_normal` — i.e.
+    /// `cross(b - a, c - a)` on the raw corners points *inward*. This is the reason
+    /// [`transformed_box_faces`] swaps `b`/`c` and `c`/`d` before triangulating; pin down the raw
+    /// table's winding here so a future edit to it can't silently flip that assumption.
+    #[test]
+    fn unit_cube_faces_raw_winding_is_inward() {
+        for &(corner_indices, local_normal) in UNIT_CUBE_FACES.iter() {
+            let [a, b, c, _d] = corner_indices.map(|i| UNIT_CUBE[i]);
+            let computed_normal = (b - a).cross(c - a).normalize();
+            assert!(
+                computed_normal.abs_diff_eq(-local_normal, 1e-6),
+                "face with local_normal {local_normal:?} should raw-wind to {:?}, got {computed_normal:?}",
+                -local_normal
+            );
+        }
+    }
+
+    /// [`transformed_box_faces`] must emit triangles wound consistently with its yielded normal,
+    /// i.e. `cross(b - a, c - a)` (for each yielded triangle's 3 vertices) points the same way as
+    /// the normal, for all 6 faces.
+    #[test]
+    fn transformed_box_faces_triangles_match_their_normal() {
+        let transform = glam::Affine3A::from_scale_rotation_translation(
+            glam::vec3(1.0, 2.0, 3.0),
+            glam::Quat::IDENTITY,
+            Vec3::ZERO,
+        );
+
+        for triangle in transformed_box_faces(transform) {
+            let [(a, normal), (b, _), (c, _)] = triangle;
+            let computed_normal = (b - a).cross(c - a).normalize();
+            assert!(
+                computed_normal.dot(normal) > 0.0,
+                "triangle {triangle:?} winds opposite its normal {normal:?}"
+            );
+        }
+    }
+}